@@ -0,0 +1,201 @@
+// RFC 3549 message framing: nlmsghdr builder, multipart/ACK parser and a
+// typed-attribute (nlattr) walker, layered on top of `NetlinkSocket`.
+
+use std::io;
+use std::mem::size_of;
+use std::ptr;
+
+pub const NLMSG_ALIGNTO: usize = 4;
+pub const NLMSG_NOOP: u16  = 1;
+pub const NLMSG_ERROR: u16 = 2;
+pub const NLMSG_DONE: u16  = 3;
+pub const NLMSG_OVERRUN: u16 = 4;
+
+pub const NLM_F_REQUEST: u16 = 0x0001;
+pub const NLM_F_MULTI: u16   = 0x0200;
+pub const NLM_F_ACK: u16     = 0x0004;
+pub const NLM_F_DUMP: u16    = 0x0300;
+
+#[inline]
+pub const fn nlmsg_align(len: usize) -> usize {
+    (len + NLMSG_ALIGNTO - 1) & !(NLMSG_ALIGNTO - 1)
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct nlmsghdr {
+    pub nlmsg_len: u32,
+    pub nlmsg_type: u16,
+    pub nlmsg_flags: u16,
+    pub nlmsg_seq: u32,
+    pub nlmsg_pid: u32,
+}
+
+pub const NLMSG_HDRLEN: usize = size_of::<nlmsghdr>();
+
+/// Builds a single, properly aligned and padded netlink datagram.
+pub struct NlMsgBuilder {
+    buf: Vec<u8>,
+}
+
+impl NlMsgBuilder {
+    pub fn new(nlmsg_type: u16, flags: u16, seq: u32, pid: u32, payload: &[u8]) -> Self {
+        let hdr = nlmsghdr {
+            nlmsg_len: (NLMSG_HDRLEN + payload.len()) as u32,
+            nlmsg_type,
+            nlmsg_flags: flags,
+            nlmsg_seq: seq,
+            nlmsg_pid: pid,
+        };
+
+        let mut buf = Vec::with_capacity(nlmsg_align(NLMSG_HDRLEN + payload.len()));
+        let hdr_bytes = unsafe {
+            std::slice::from_raw_parts(&hdr as *const nlmsghdr as *const u8, NLMSG_HDRLEN)
+        };
+        buf.extend_from_slice(hdr_bytes);
+        buf.extend_from_slice(payload);
+
+        let padded_len = nlmsg_align(buf.len());
+        buf.resize(padded_len, 0);
+
+        Self { buf }
+    }
+
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf
+    }
+
+    #[inline]
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+/// A single decoded message out of a received buffer.
+#[derive(Debug)]
+pub enum NlMsg<'a> {
+    Ack,
+    Error(i32),
+    Done,
+    Data { header: nlmsghdr, payload: &'a [u8] },
+}
+
+/// Walks a received buffer frame-by-frame per RFC 3549, stopping at
+/// `NLMSG_DONE` / `NLMSG_ERROR` the way a multipart dump terminates.
+#[derive(Debug)]
+pub struct NlMsgIter<'a> {
+    buf: &'a [u8],
+    offset: usize,
+    done: bool,
+}
+
+impl<'a> NlMsgIter<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self { buf, offset: 0, done: false }
+    }
+}
+
+impl<'a> Iterator for NlMsgIter<'a> {
+    type Item = Result<NlMsg<'a>, io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || self.offset + NLMSG_HDRLEN > self.buf.len() {
+            return None;
+        }
+
+        let hdr = unsafe {
+            ptr::read_unaligned(self.buf[self.offset..].as_ptr() as *const nlmsghdr)
+        };
+
+        let msg_len = hdr.nlmsg_len as usize;
+        if msg_len < NLMSG_HDRLEN || self.offset + msg_len > self.buf.len() {
+            self.done = true;
+            return Some(Err(io::Error::new(io::ErrorKind::InvalidData, "malformed nlmsg_len")));
+        }
+
+        let payload = &self.buf[self.offset + NLMSG_HDRLEN..self.offset + msg_len];
+        self.offset += nlmsg_align(msg_len);
+
+        let msg = match hdr.nlmsg_type {
+            NLMSG_ERROR => {
+                if payload.len() < 4 {
+                    self.done = true;
+                    return Some(Err(io::Error::new(io::ErrorKind::InvalidData, "truncated NLMSG_ERROR")));
+                }
+                let errno = i32::from_ne_bytes([payload[0], payload[1], payload[2], payload[3]]);
+                self.done = true;
+                if errno == 0 { NlMsg::Ack } else { NlMsg::Error(errno) }
+            },
+            NLMSG_DONE => {
+                self.done = true;
+                NlMsg::Done
+            },
+            // A plain (non-MULTI) data frame only ends *this* message, not
+            // the buffer: further frames may still follow it, so leave
+            // `self.done` alone and let the offset/length check above drive
+            // end-of-buffer.
+            _ => NlMsg::Data { header: hdr, payload },
+        };
+
+        Some(Ok(msg))
+    }
+}
+
+pub const NLA_ALIGNTO: usize = 4;
+pub const NLA_F_NESTED: u16        = 0x8000;
+pub const NLA_F_NET_BYTEORDER: u16 = 0x4000;
+pub const NLA_TYPE_MASK: u16       = !(NLA_F_NESTED | NLA_F_NET_BYTEORDER);
+
+#[inline]
+pub const fn nla_align(len: usize) -> usize {
+    (len + NLA_ALIGNTO - 1) & !(NLA_ALIGNTO - 1)
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct nlattr {
+    pub nla_len: u16,
+    pub nla_type: u16,
+}
+
+pub const NLA_HDRLEN: usize = size_of::<nlattr>();
+
+/// Walks a `nlattr`-framed attribute blob (4-byte aligned).
+#[derive(Debug)]
+pub struct NlAttrIter<'a> {
+    buf: &'a [u8],
+    offset: usize,
+    done: bool,
+}
+
+impl<'a> NlAttrIter<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self { buf, offset: 0, done: false }
+    }
+}
+
+impl<'a> Iterator for NlAttrIter<'a> {
+    type Item = Result<(nlattr, &'a [u8]), io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || self.offset + NLA_HDRLEN > self.buf.len() {
+            return None;
+        }
+
+        let attr = unsafe {
+            ptr::read_unaligned(self.buf[self.offset..].as_ptr() as *const nlattr)
+        };
+
+        let attr_len = attr.nla_len as usize;
+        if attr_len < NLA_HDRLEN || self.offset + attr_len > self.buf.len() {
+            self.done = true;
+            return Some(Err(io::Error::new(io::ErrorKind::InvalidData, "malformed nla_len")));
+        }
+
+        let payload = &self.buf[self.offset + NLA_HDRLEN..self.offset + attr_len];
+        self.offset += nla_align(attr_len);
+
+        Some(Ok((attr, payload)))
+    }
+}
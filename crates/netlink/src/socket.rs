@@ -3,7 +3,10 @@
 
 use libc;
 
-use std::io::{self, Read, Write};
+use crate::nlmsg::{NlMsg, NlMsgBuilder, NlMsgIter, NLM_F_MULTI};
+
+use std::io::{self, IoSlice, IoSliceMut, Read, Write};
+use std::os::fd::{AsFd, BorrowedFd, FromRawFd, OwnedFd};
 use std::os::unix::io::{AsRawFd, IntoRawFd, RawFd};
 
 
@@ -33,13 +36,15 @@ pub struct sockaddr_nl {
 
 #[derive(Debug)]
 pub struct NetlinkSocket {
-    fd: libc::c_int,
+    fd: OwnedFd,
+    pid: u32,
+    seq: u32,
 }
 
 impl NetlinkSocket {
     pub fn new(proto: i32) -> Result<Self, io::Error> {
         // http://man7.org/linux/man-pages/man7/netlink.7.html
-        // 
+        //
         // Netlink is a datagram-oriented service.  Both SOCK_RAW and SOCK_DGRAM
         // are valid values for socket_type.  However, the netlink protocol does
         // not distinguish between datagram and raw sockets.
@@ -48,7 +53,7 @@ impl NetlinkSocket {
             return Err(io::Error::last_os_error());
         }
 
-        Ok(Self { fd })
+        Ok(Self { fd: unsafe { OwnedFd::from_raw_fd(fd) }, pid: 0, seq: 0 })
     }
 
     pub fn bind(&mut self, pid: u32, groups: u32) -> Result<(), io::Error> {
@@ -62,16 +67,72 @@ impl NetlinkSocket {
         let nladdr_ptr = &nladdr as *const sockaddr_nl as  *const libc::sockaddr;
         let sa_len = std::mem::size_of::<sockaddr_nl>() as u32;
 
-        if unsafe { libc::bind(self.fd, nladdr_ptr, sa_len) } < 0 {
+        if unsafe { libc::bind(self.fd.as_raw_fd(), nladdr_ptr, sa_len) } < 0 {
             return Err(io::Error::last_os_error());
         }
 
+        self.pid = pid;
         Ok(())
     }
 
+    /// The pid this socket bound, stamped into every `NlMsgBuilder` frame.
+    #[inline]
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// Advances and returns this socket's per-socket nlmsg_seq counter.
+    #[inline]
+    pub fn next_seq(&mut self) -> u32 {
+        self.seq += 1;
+        self.seq
+    }
+
+    /// Frames `payload` as a single netlink message and sends it, stamping
+    /// the bound pid and an auto-incrementing sequence number.
+    pub fn send_msg(&mut self, nlmsg_type: u16, flags: u16, payload: &[u8]) -> Result<usize, io::Error> {
+        let pid = self.pid;
+        let seq = self.next_seq();
+        let msg = NlMsgBuilder::new(nlmsg_type, flags, seq, pid, payload);
+        self.send(msg.as_bytes(), 0)
+    }
+
+    /// Receives a (possibly multipart) response into `buf`, collecting
+    /// `NLMSG_DONE`-terminated `Data` payloads and surfacing `NLMSG_ERROR`.
+    ///
+    /// A reply that carries no `NLM_F_MULTI` frame (e.g. a plain, non-dump
+    /// request/response) has no further message coming and is not followed
+    /// by `NLMSG_DONE`/an ACK, so the loop also stops once a `recv`'d buffer
+    /// turns out to hold none.
+    pub fn recv_multipart(&mut self, buf: &mut [u8]) -> Result<Vec<Vec<u8>>, io::Error> {
+        let mut payloads = Vec::new();
+
+        'recv: loop {
+            let n = self.recv(buf, 0)?;
+            let mut saw_multi = false;
+
+            for msg in NlMsgIter::new(&buf[..n]) {
+                match msg? {
+                    NlMsg::Data { header, payload } => {
+                        saw_multi |= header.nlmsg_flags & NLM_F_MULTI != 0;
+                        payloads.push(payload.to_vec());
+                    },
+                    NlMsg::Done | NlMsg::Ack => break 'recv,
+                    NlMsg::Error(errno) => return Err(io::Error::from_raw_os_error(errno.abs())),
+                }
+            }
+
+            if !saw_multi {
+                break;
+            }
+        }
+
+        Ok(payloads)
+    }
+
     #[inline]
     pub fn flags(&self) -> Result<i32, io::Error> {
-        let flags = unsafe { libc::fcntl(self.fd, libc::F_GETFL, 0) };
+        let flags = unsafe { libc::fcntl(self.fd.as_raw_fd(), libc::F_GETFL, 0) };
         if flags < 0 {
             return Err(io::Error::last_os_error());
         }
@@ -81,7 +142,7 @@ impl NetlinkSocket {
     
     #[inline]
     pub fn set_flags(&mut self, flags: i32) -> Result<(), io::Error> {
-        if unsafe { libc::fcntl(self.fd, libc::F_SETFL, flags) } < 0 {
+        if unsafe { libc::fcntl(self.fd.as_raw_fd(), libc::F_SETFL, flags) } < 0 {
             return Err(io::Error::last_os_error());
         }
         Ok(())
@@ -112,7 +173,7 @@ impl NetlinkSocket {
         let groups_ptr = &groups as *const u32 as *const libc::c_void;
         let groups_len = std::mem::size_of::<u32>() as libc::socklen_t;
         let ret = unsafe {
-            libc::setsockopt(self.fd, SOL_NETLINK, NETLINK_ADD_MEMBERSHIP, groups_ptr, groups_len)
+            libc::setsockopt(self.fd.as_raw_fd(), SOL_NETLINK, NETLINK_ADD_MEMBERSHIP, groups_ptr, groups_len)
         };
 
         if ret != 0 {
@@ -126,7 +187,7 @@ impl NetlinkSocket {
         let buf_ptr = buf.as_ptr() as *const libc::c_void;
         let buf_len = buf.len();
 
-        let amt = unsafe { libc::send(self.fd, buf_ptr, buf_len, flags) };
+        let amt = unsafe { libc::send(self.fd.as_raw_fd(), buf_ptr, buf_len, flags) };
         if amt < 0 {
             return Err(io::Error::last_os_error());
         }
@@ -138,7 +199,7 @@ impl NetlinkSocket {
         let buf_ptr = buf.as_mut_ptr() as *mut libc::c_void;
         let buf_len = buf.len();
 
-        let amt = unsafe { libc::recv(self.fd, buf_ptr, buf_len, flags) };
+        let amt = unsafe { libc::recv(self.fd.as_raw_fd(), buf_ptr, buf_len, flags) };
         if amt < 0 {
             return Err(io::Error::last_os_error());
         }
@@ -158,6 +219,59 @@ impl NetlinkSocket {
         Ok(amt)
     }
 
+    /// Scatter-gather send over `sendmsg(2)`.
+    pub fn sendmsg(&mut self, bufs: &[IoSlice], flags: i32) -> Result<usize, io::Error> {
+        let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+        msg.msg_iov = bufs.as_ptr() as *mut libc::iovec;
+        msg.msg_iovlen = bufs.len() as _;
+
+        let amt = unsafe { libc::sendmsg(self.fd.as_raw_fd(), &msg, flags) };
+        if amt < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(amt as usize)
+    }
+
+    /// Scatter-gather receive over `recvmsg(2)`, returning the number of
+    /// bytes written into `bufs` and the kernel's `msg_flags`. Callers
+    /// should check the returned flags for `MSG_TRUNC` (or call
+    /// [`NetlinkSocket::recvmsg_checked`], which does it for them).
+    pub fn recvmsg(&mut self, bufs: &mut [IoSliceMut], flags: i32) -> Result<(usize, i32), io::Error> {
+        let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+        msg.msg_iov = bufs.as_mut_ptr() as *mut libc::iovec;
+        msg.msg_iovlen = bufs.len() as _;
+
+        let amt = unsafe { libc::recvmsg(self.fd.as_raw_fd(), &mut msg, flags) };
+        if amt < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok((amt as usize, msg.msg_flags))
+    }
+
+    /// Like [`NetlinkSocket::recvmsg`], but turns a truncated datagram
+    /// (`MSG_TRUNC` set in the returned flags) into a distinct error
+    /// instead of silently handing back a clipped buffer.
+    pub fn recvmsg_checked(&mut self, bufs: &mut [IoSliceMut], flags: i32) -> Result<usize, io::Error> {
+        let (amt, msg_flags) = self.recvmsg(bufs, flags)?;
+        if msg_flags & libc::MSG_TRUNC != 0 {
+            return Err(io::Error::new(io::ErrorKind::Other, "MSG_TRUNC: netlink datagram was truncated"));
+        }
+
+        Ok(amt)
+    }
+
+    /// Sizes the next pending datagram without consuming it, via
+    /// `MSG_PEEK | MSG_TRUNC`: on Linux the latter makes `recvmsg(2)`
+    /// report the full datagram length even when the probe buffer is empty.
+    pub fn peek_size(&mut self) -> Result<usize, io::Error> {
+        let mut probe: [u8; 0] = [];
+        let mut iov = [IoSliceMut::new(&mut probe)];
+        let (amt, _flags) = self.recvmsg(&mut iov, libc::MSG_PEEK | libc::MSG_TRUNC)?;
+        Ok(amt)
+    }
+
     pub fn recv2<T: Sized>(&mut self, buf: &mut T) -> Result<usize, io::Error> {
         let buf_len = std::mem::size_of::<T>();
         let buf_ptr = buf as *mut T as *mut u8;
@@ -171,15 +285,39 @@ impl NetlinkSocket {
 }
 
 
+impl NetlinkSocket {
+    /// Wraps an already-open netlink socket, taking ownership of `fd`.
+    ///
+    /// # Safety
+    /// `fd` must refer to a valid, open netlink socket not owned elsewhere.
+    pub unsafe fn from_raw_fd(fd: RawFd) -> Self {
+        Self { fd: OwnedFd::from_raw_fd(fd), pid: 0, seq: 0 }
+    }
+}
+
+impl From<OwnedFd> for NetlinkSocket {
+    fn from(fd: OwnedFd) -> Self {
+        Self { fd, pid: 0, seq: 0 }
+    }
+}
+
+impl AsFd for NetlinkSocket {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
 impl AsRawFd for NetlinkSocket {
     fn as_raw_fd(&self) -> RawFd {
-        self.fd
+        self.fd.as_raw_fd()
     }
 }
 
 impl IntoRawFd for NetlinkSocket {
     fn into_raw_fd(self) -> RawFd {
-        self.fd
+        // `OwnedFd::into_raw_fd` relinquishes ownership, so the fd is not
+        // closed when `self` (and its `fd` field) is dropped right after.
+        self.fd.into_raw_fd()
     }
 }
 
@@ -198,10 +336,3 @@ impl Write for NetlinkSocket {
         Ok(())
     }
 }
-
-impl Drop for NetlinkSocket {
-    fn drop(&mut self) {
-        unsafe { libc::close(self.fd) };
-        trace!("close({})", self.fd);
-    }
-}
@@ -0,0 +1,315 @@
+use crate::Value;
+
+use libc;
+
+use std::ptr;
+use std::str::FromStr;
+use std::io;
+use std::ffi::CString;
+use std::convert::TryInto;
+use std::mem::size_of;
+
+use super::linux::Kind;
+
+
+// largest number of components supported
+pub const CTL_MAXNAME: usize = 24;
+
+// Top-level names ( match <sys/sysctl.h> )
+pub const CTL_KERN: libc::c_int  = 1; // General kernel info and control
+pub const CTL_VM: libc::c_int    = 2; // VM management
+pub const CTL_NET: libc::c_int   = 4; // Networking
+pub const CTL_HW: libc::c_int    = 6; // Generic CPU/io
+pub const CTL_MACHDEP: libc::c_int = 7; // Machine dependent
+pub const CTL_USER: libc::c_int  = 8; // User-level
+
+// Meta nodes used to query a mib's `CTLTYPE`/format string ( match
+// <sys/sysctl.h> CTL_SYSCTL / CTL_SYSCTL_OIDFMT ).
+const CTL_SYSCTL: libc::c_int        = 0;
+const CTL_SYSCTL_OIDFMT: libc::c_int = 4;
+
+const CTLTYPE_MASK: u32   = 0xf;
+const CTLTYPE_NODE: u32   = 1;
+const CTLTYPE_INT: u32    = 2;
+const CTLTYPE_STRING: u32 = 3;
+const CTLTYPE_S64: u32    = 4;
+const CTLTYPE_OPAQUE: u32 = 5;
+const CTLTYPE_UINT: u32   = 6;
+const CTLTYPE_LONG: u32   = 7;
+const CTLTYPE_ULONG: u32  = 8;
+const CTLTYPE_U64: u32    = 9;
+const CTLTYPE_U8: u32     = 0xa;
+const CTLTYPE_U16: u32    = 0xb;
+const CTLTYPE_S8: u32     = 0xc;
+const CTLTYPE_S16: u32    = 0xd;
+const CTLTYPE_S32: u32    = 0xe;
+const CTLTYPE_U32: u32    = 0xf;
+
+
+#[derive(Debug)]
+pub struct Metadata {
+    kind: Kind,
+    // Queried at runtime via CTL_SYSCTL_OIDFMT, so unlike the Linux
+    // backend's table-sourced `&'static str` this is owned.
+    indication: String,
+}
+
+impl Metadata {
+    #[inline]
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    #[inline]
+    pub fn indication(&self) -> &str {
+        &self.indication
+    }
+}
+
+
+#[derive(Debug, Clone)]
+pub struct Mib {
+    oid: Vec<libc::c_int>,
+}
+
+impl Mib {
+    #[inline]
+    pub fn components(&self) -> &[libc::c_int] {
+        &self.oid
+    }
+
+    pub fn name(&self) -> Result<String, io::Error> {
+        // CTL_NAME isn't available on every BSD, so the oid is rendered back
+        // as a dotted numeric string instead of reversing it to a name.
+        Ok(self.oid.iter().map(|n| n.to_string()).collect::<Vec<_>>().join("."))
+    }
+
+    // Get raw Value by Mib, via two `sysctl(2)` calls: the first with a
+    // null `oldp` to learn the buffer length, the second to fill it.
+    pub fn value(&self) -> Result<Vec<u8>, io::Error> {
+        let mut len: libc::size_t = 0;
+
+        let ret = unsafe {
+            libc::sysctl(
+                self.oid.as_ptr() as *mut libc::c_int,
+                self.oid.len() as libc::c_uint,
+                ptr::null_mut(),
+                &mut len,
+                ptr::null(),
+                0,
+            )
+        };
+        if ret != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut buf: Vec<u8> = vec![0u8; len];
+        let ret = unsafe {
+            libc::sysctl(
+                self.oid.as_ptr() as *mut libc::c_int,
+                self.oid.len() as libc::c_uint,
+                buf.as_mut_ptr() as *mut libc::c_void,
+                &mut len,
+                ptr::null(),
+                0,
+            )
+        };
+        if ret != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        buf.truncate(len);
+        Ok(buf)
+    }
+
+    // Set Value By Mib
+    pub fn set_value(&self, val: &[u8]) -> Result<Vec<u8>, io::Error> {
+        let ret = unsafe {
+            libc::sysctl(
+                self.oid.as_ptr() as *mut libc::c_int,
+                self.oid.len() as libc::c_uint,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                val.as_ptr() as *const libc::c_void,
+                val.len(),
+            )
+        };
+        if ret != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        self.value()
+    }
+
+    // Query the mib's `CTLTYPE`/format string via the CTL_SYSCTL_OIDFMT meta
+    // node (`sysctl -> oidfmt` in userspace), falling back to `Kind::Unknow`
+    // if the platform doesn't expose it.
+    pub fn metadata(&self) -> Result<Metadata, io::Error> {
+        let mut oid = Vec::with_capacity(self.oid.len() + 2);
+        oid.push(CTL_SYSCTL);
+        oid.push(CTL_SYSCTL_OIDFMT);
+        oid.extend_from_slice(&self.oid);
+
+        let mut len: libc::size_t = 0;
+        let ret = unsafe {
+            libc::sysctl(oid.as_ptr() as *mut libc::c_int, oid.len() as libc::c_uint,
+                ptr::null_mut(), &mut len, ptr::null(), 0)
+        };
+        if ret != 0 {
+            return Ok(Metadata { kind: Kind::Unknow, indication: String::from("?") });
+        }
+
+        let mut buf = vec![0u8; len];
+        let ret = unsafe {
+            libc::sysctl(oid.as_ptr() as *mut libc::c_int, oid.len() as libc::c_uint,
+                buf.as_mut_ptr() as *mut libc::c_void, &mut len, ptr::null(), 0)
+        };
+        if ret != 0 || len < 4 {
+            return Ok(Metadata { kind: Kind::Unknow, indication: String::from("?") });
+        }
+
+        // `c_long`/`c_ulong` are pointer-width on every real BSD/macOS target
+        // (4 bytes on ILP32, 8 on LP64): map them to the Kind whose width
+        // actually matches what `sysctl(2)` will hand back, instead of
+        // always assuming 64-bit.
+        let long_is_64bit = size_of::<libc::c_long>() == 8;
+
+        let raw_kind = u32::from_ne_bytes([buf[0], buf[1], buf[2], buf[3]]);
+        let kind = match raw_kind & CTLTYPE_MASK {
+            CTLTYPE_NODE => Kind::Node,
+            CTLTYPE_INT | CTLTYPE_S32 => Kind::I32,
+            CTLTYPE_UINT | CTLTYPE_U32 => Kind::U32,
+            CTLTYPE_STRING => Kind::String,
+            CTLTYPE_S64 => Kind::I64,
+            CTLTYPE_U64 => Kind::U64,
+            CTLTYPE_LONG => if long_is_64bit { Kind::I64 } else { Kind::I32 },
+            CTLTYPE_ULONG => if long_is_64bit { Kind::U64 } else { Kind::U32 },
+            CTLTYPE_U8 => Kind::U8,
+            CTLTYPE_U16 => Kind::U16,
+            CTLTYPE_S8 => Kind::I8,
+            CTLTYPE_S16 => Kind::I16,
+            CTLTYPE_OPAQUE => Kind::Struct,
+            _ => Kind::Unknow,
+        };
+
+        // The format string (e.g. "I", "A", "S,if_data") follows the u32
+        // kind, NUL-terminated.
+        let fmt = buf[4..len].split(|b| *b == 0).next().unwrap_or(&[]);
+        let indication = String::from_utf8_lossy(fmt).into_owned();
+
+        Ok(Metadata { kind, indication })
+    }
+
+    // Get Value by Mib, parsed according to the `Kind` looked up via
+    // CTL_SYSCTL_OIDFMT. Unlike the Linux `/proc/sys` backend, `sysctl(2)`
+    // hands back values already in their native binary form, not ASCII.
+    pub fn typed_value(&self) -> Result<Value, io::Error> {
+        let kind = self.metadata()?.kind;
+        let raw = self.value()?;
+        Self::parse_bytes(kind, &raw)
+    }
+
+    // Set Value By Mib, serialized to the native binary form `sysctl(2)` expects.
+    pub fn set_typed_value(&self, v: &Value) -> Result<(), io::Error> {
+        let bytes = Self::value_to_bytes(v);
+        self.set_value(&bytes)?;
+        Ok(())
+    }
+
+    fn parse_bytes(kind: Kind, raw: &[u8]) -> Result<Value, io::Error> {
+        macro_rules! parse_int {
+            ($variant:ident, $ty:ty) => {{
+                let bytes: [u8; std::mem::size_of::<$ty>()] = raw.get(..std::mem::size_of::<$ty>())
+                    .and_then(|s| s.try_into().ok())
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "value too short for its Kind"))?;
+                Ok(Value::$variant(<$ty>::from_ne_bytes(bytes)))
+            }};
+        }
+
+        match kind {
+            Kind::String => {
+                let s = raw.split(|b| *b == 0).next().unwrap_or(raw);
+                String::from_utf8(s.to_vec())
+                    .map(Value::String)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            },
+            Kind::I8 => parse_int!(I8, i8),
+            Kind::I16 => parse_int!(I16, i16),
+            Kind::I32 => parse_int!(I32, i32),
+            Kind::I64 => parse_int!(I64, i64),
+            Kind::U8 => parse_int!(U8, u8),
+            Kind::U16 => parse_int!(U16, u16),
+            Kind::U32 => parse_int!(U32, u32),
+            Kind::U64 => parse_int!(U64, u64),
+            Kind::Node | Kind::Struct | Kind::Unknow => {
+                Err(io::Error::new(io::ErrorKind::InvalidInput, "Can not parse value of this Kind."))
+            },
+        }
+    }
+
+    fn value_to_bytes(v: &Value) -> Vec<u8> {
+        match v {
+            Value::String(s) => {
+                let mut bytes = s.clone().into_bytes();
+                bytes.push(0);
+                bytes
+            },
+            Value::I8(n) => n.to_ne_bytes().to_vec(),
+            Value::I16(n) => n.to_ne_bytes().to_vec(),
+            Value::I32(n) => n.to_ne_bytes().to_vec(),
+            Value::I64(n) => n.to_ne_bytes().to_vec(),
+            Value::U8(n) => n.to_ne_bytes().to_vec(),
+            Value::U16(n) => n.to_ne_bytes().to_vec(),
+            Value::U32(n) => n.to_ne_bytes().to_vec(),
+            Value::U64(n) => n.to_ne_bytes().to_vec(),
+            Value::Vec(values) => values.iter().flat_map(Self::value_to_bytes).collect(),
+        }
+    }
+
+    #[inline]
+    pub fn description(&self) -> Result<String, io::Error> {
+        Err(io::Error::new(io::ErrorKind::Other, "Description not available"))
+    }
+}
+
+impl FromStr for Mib {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // A dotted-numeric oid (e.g. "1.2.3") is used as-is, anything else
+        // is resolved through `sysctlnametomib(3)`.
+        if s.chars().all(|c| c.is_ascii_digit() || c == '.') {
+            let oid = s.split('.')
+                .map(|part| part.parse::<libc::c_int>()
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e)))
+                .collect::<Result<Vec<_>, io::Error>>()?;
+
+            if oid.is_empty() || oid.len() > CTL_MAXNAME {
+                return Err(io::Error::from(io::ErrorKind::InvalidInput));
+            }
+
+            return Ok(Self { oid });
+        }
+
+        let name = CString::new(s)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+        let mut oid: [libc::c_int; CTL_MAXNAME] = [0; CTL_MAXNAME];
+        let mut len: libc::size_t = CTL_MAXNAME;
+
+        let ret = unsafe {
+            libc::sysctlnametomib(name.as_ptr(), oid.as_mut_ptr(), &mut len)
+        };
+        if ret != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self { oid: oid[..len].to_vec() })
+    }
+}
+
+impl Default for Mib {
+    fn default() -> Self {
+        Self { oid: vec![CTL_KERN] }
+    }
+}
@@ -7,6 +7,7 @@ use std::str::FromStr;
 use std::io::{self, Read, Write};
 use std::fs::{OpenOptions, ReadDir,};
 use std::path::{Path, PathBuf,};
+use std::collections::HashSet;
 
 
 // largest number of components supported
@@ -40,17 +41,21 @@ pub const CTL_PM: libc::c_int = 9899; // frv power management
 pub const CTL_FRV: libc::c_int = 9898; // frv specific sysctls
 
 
-// TODO:
 // Metadata Table
-pub const TABLE: &[(&'static str, Kind)] = &[
-    ("kernel", Kind::Node,),
-    ("kernel.ostype", Kind::I32,),
-    ("kernel.version", Kind::I32,),
-    ("kernel.osrelease", Kind::String,),
+//
+// Each entry maps a dotted sysctl name to its `Kind` and the FreeBSD-style
+// format indication (`I` for integer, `A` for string, `S` for an opaque
+// struct, ...) used when no richer description is available.
+pub const TABLE: &[(&'static str, Kind, &'static str)] = &[
+    ("kernel", Kind::Node, "N",),
+    ("kernel.ostype", Kind::String, "A",),
+    ("kernel.version", Kind::String, "A",),
+    ("kernel.osrelease", Kind::String, "A",),
+    ("kernel.printk", Kind::I32, "I",),
 ];
 
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Kind {
     Node,
     String,
@@ -73,6 +78,18 @@ pub struct Metadata {
     indication: &'static str,
 }
 
+impl Metadata {
+    #[inline]
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    #[inline]
+    pub fn indication(&self) -> &'static str {
+        self.indication
+    }
+}
+
 
 #[derive(Debug, Clone)]
 pub struct Mib {
@@ -82,8 +99,9 @@ pub struct Mib {
 impl Mib {
     #[inline]
     pub fn components(&self) -> &[libc::c_int] {
-        // &self.inner[..self.len]
-        unimplemented!()
+        // Linux addresses sysctls by `/proc/sys` path, not a numeric oid,
+        // so there's no component array to hand back here.
+        &[]
     }
 
     pub fn name(&self) -> Result<String, io::Error> {
@@ -115,7 +133,84 @@ impl Mib {
 
     // Get metadata ( ValueKind )
     pub fn metadata(&self) -> Result<Metadata, io::Error> {
-        unimplemented!()
+        let name = self.name()?;
+        let (kind, indication) = TABLE.iter()
+            .find(|(n, _, _)| *n == name)
+            .map(|(_, kind, indication)| (*kind, *indication))
+            .unwrap_or((Kind::Unknow, "?"));
+
+        Ok(Metadata { kind, indication })
+    }
+
+    // Get Value by Mib, parsed according to the `Kind` looked up in `TABLE`.
+    pub fn typed_value(&self) -> Result<Value, io::Error> {
+        let kind = self.metadata()?.kind;
+        let raw = self.value()?;
+        let text = String::from_utf8_lossy(&raw);
+        let text = text.trim_end_matches('\n');
+
+        let tokens: Vec<&str> = text.split(|c: char| c == '\t' || c == ' ')
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        if kind != Kind::String && tokens.len() > 1 {
+            let values = tokens.iter()
+                .map(|tok| Self::parse_scalar(kind, tok))
+                .collect::<Result<Vec<_>, io::Error>>()?;
+            Ok(Value::Vec(values))
+        } else {
+            Self::parse_scalar(kind, text)
+        }
+    }
+
+    // Set Value By Mib, serialized to the textual format the kernel expects.
+    pub fn set_typed_value(&self, v: &Value) -> Result<(), io::Error> {
+        let text = Self::format_value(v);
+        self.set_value(text.as_bytes())?;
+        Ok(())
+    }
+
+    fn parse_scalar(kind: Kind, tok: &str) -> Result<Value, io::Error> {
+        macro_rules! parse {
+            ($variant:ident, $ty:ty) => {
+                tok.parse::<$ty>()
+                    .map(Value::$variant)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            };
+        }
+
+        match kind {
+            Kind::String => Ok(Value::String(tok.to_string())),
+            Kind::I8 => parse!(I8, i8),
+            Kind::I16 => parse!(I16, i16),
+            Kind::I32 => parse!(I32, i32),
+            Kind::I64 => parse!(I64, i64),
+            Kind::U8 => parse!(U8, u8),
+            Kind::U16 => parse!(U16, u16),
+            Kind::U32 => parse!(U32, u32),
+            Kind::U64 => parse!(U64, u64),
+            Kind::Node | Kind::Struct | Kind::Unknow => {
+                Err(io::Error::new(io::ErrorKind::InvalidInput, "Can not parse value of this Kind."))
+            },
+        }
+    }
+
+    fn format_value(v: &Value) -> String {
+        match v {
+            Value::String(s) => s.clone(),
+            Value::I8(n) => n.to_string(),
+            Value::I16(n) => n.to_string(),
+            Value::I32(n) => n.to_string(),
+            Value::I64(n) => n.to_string(),
+            Value::U8(n) => n.to_string(),
+            Value::U16(n) => n.to_string(),
+            Value::U32(n) => n.to_string(),
+            Value::U64(n) => n.to_string(),
+            Value::Vec(values) => values.iter()
+                .map(Self::format_value)
+                .collect::<Vec<_>>()
+                .join("\t"),
+        }
     }
 
     #[inline]
@@ -165,6 +260,10 @@ impl Default for Mib {
 #[derive(Debug)]
 pub struct MibIter {
     dirs: Vec<ReadDir>,
+    // Canonical paths of every directory already descended into, so a
+    // symlink loop (the kernel exposes some self-referential ones) cannot
+    // send the walk into infinite recursion.
+    visited: HashSet<PathBuf>,
 }
 
 impl MibIter {
@@ -175,7 +274,10 @@ impl MibIter {
         let mut dirs = Vec::new();
         dirs.push(root.read_dir()?);
 
-        fn seek(dirs: &mut Vec<ReadDir>, stop_path: &Path) -> Result<(), io::Error> {
+        let mut visited = HashSet::new();
+        visited.insert(root.canonicalize()?);
+
+        fn seek(dirs: &mut Vec<ReadDir>, visited: &mut HashSet<PathBuf>, stop_path: &Path) -> Result<(), io::Error> {
             if dirs.len() == 0 {
                 return Ok(());
             }
@@ -185,48 +287,102 @@ impl MibIter {
                 Some(dir) => dir,
                 None => return Ok(()),
             };
-            
+
             loop {
                 let entry = dir.next();
                 if entry.is_none() {
                     dirs.remove(idx);
-                    return seek(dirs, stop_path);
+                    return seek(dirs, visited, stop_path);
                 }
 
                 let entry = entry.unwrap()?;
                 let file_type = entry.file_type()?;
                 let file_path = entry.path();
-                
-                if file_type.is_dir() {
+
+                if file_type.is_symlink() {
+                    match resolve_symlink(&file_path, visited)? {
+                        Some(SymlinkTarget::Dir(target)) => {
+                            dirs.push(target.read_dir()?);
+                            if file_path == stop_path || target == stop_path {
+                                break;
+                            }
+
+                            return seek(dirs, visited, stop_path);
+                        },
+                        Some(SymlinkTarget::File(_)) => {
+                            if file_path == stop_path {
+                                break;
+                            }
+                        },
+                        None => {
+                            // Escapes PATH_PREFIX or revisits an already
+                            // walked directory: skip it.
+                            continue;
+                        },
+                    }
+                } else if file_type.is_dir() {
+                    visited.insert(file_path.canonicalize()?);
                     dirs.push(file_path.read_dir()?);
                     if file_path == stop_path {
                         break;
                     }
 
-                    return seek(dirs, stop_path);
+                    return seek(dirs, visited, stop_path);
 
                 } else if file_type.is_file() {
-                    // println!("Skip: {:?}", file_path);
                     if file_path == stop_path {
                         break;
                     }
                 } else {
-                    // TODO: symlink
-                    unimplemented!()
+                    continue;
                 }
             }
 
             Ok(())
         }
 
-        seek(&mut dirs, &path)?;
-        
+        seek(&mut dirs, &mut visited, &path)?;
+
         Ok(MibIter {
             dirs: dirs,
+            visited: visited,
         })
     }
 }
 
+enum SymlinkTarget {
+    Dir(PathBuf),
+    File(PathBuf),
+}
+
+// Resolves a symlink entry to its canonical target, rejecting targets that
+// escape `PATH_PREFIX` and ones already in `visited` (a cycle). Directory
+// targets are recorded into `visited` before being returned.
+fn resolve_symlink(file_path: &Path, visited: &mut HashSet<PathBuf>) -> Result<Option<SymlinkTarget>, io::Error> {
+    let target = match file_path.canonicalize() {
+        Ok(target) => target,
+        // A dangling symlink (the kernel exposes a few) isn't walkable.
+        Err(_) => return Ok(None),
+    };
+
+    if !target.starts_with(PATH_PREFIX) {
+        return Ok(None);
+    }
+
+    if visited.contains(&target) {
+        return Ok(None);
+    }
+
+    if target.is_dir() {
+        visited.insert(target.clone());
+        Ok(Some(SymlinkTarget::Dir(target)))
+    } else if target.is_file() {
+        Ok(Some(SymlinkTarget::File(target)))
+    } else {
+        Ok(None)
+    }
+}
+
 impl Iterator for MibIter {
     type Item = Result<Mib, std::io::Error>;
 
@@ -245,8 +401,28 @@ impl Iterator for MibIter {
                     Err(e) => return Some(Err(e)),
                 };
                 let file_path = entry.path();
-                
-                if file_type.is_dir() {
+
+                if file_type.is_symlink() {
+                    match resolve_symlink(&file_path, &mut self.visited) {
+                        Ok(Some(SymlinkTarget::Dir(target))) => {
+                            match target.read_dir() {
+                                Ok(sub_dir) => self.dirs.push(sub_dir),
+                                Err(e) => return Some(Err(e)),
+                            }
+                            self.next()
+                        },
+                        Ok(Some(SymlinkTarget::File(target))) => {
+                            let s = target.to_string_lossy().to_string();
+                            Some(Mib::from_str(&s))
+                        },
+                        Ok(None) => self.next(),
+                        Err(e) => Some(Err(e)),
+                    }
+                } else if file_type.is_dir() {
+                    match file_path.canonicalize() {
+                        Ok(canonical) => { self.visited.insert(canonical); },
+                        Err(e) => return Some(Err(e)),
+                    }
                     match file_path.read_dir() {
                         Ok(sub_dir) => self.dirs.push(sub_dir),
                         Err(e) => return Some(Err(e)),
@@ -256,8 +432,7 @@ impl Iterator for MibIter {
                     let s = file_path.to_string_lossy().to_string();
                     Some(Mib::from_str(&s))
                 } else {
-                    // TODO: hanlde symlink
-                    unimplemented!()
+                    self.next()
                 }
             },
             Some(Err(e)) => return Some(Err(e)),